@@ -0,0 +1,33 @@
+use bpe_match::{find_matches, find_tokens};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const ASCII_PROSE: &str = include_str!("corpora/ascii_prose.txt");
+const UNICODE_MIXED: &str = include_str!("corpora/unicode_mixed.txt");
+const NUMERIC: &str = include_str!("corpora/numeric.txt");
+
+fn bench_find_matches(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_matches");
+    for (name, corpus) in [
+        ("ascii_prose", ASCII_PROSE),
+        ("unicode_mixed", UNICODE_MIXED),
+        ("numeric", NUMERIC),
+    ] {
+        group.bench_function(name, |b| b.iter(|| find_matches(black_box(corpus))));
+    }
+    group.finish();
+}
+
+fn bench_find_tokens(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_tokens");
+    for (name, corpus) in [
+        ("ascii_prose", ASCII_PROSE),
+        ("unicode_mixed", UNICODE_MIXED),
+        ("numeric", NUMERIC),
+    ] {
+        group.bench_function(name, |b| b.iter(|| find_tokens(black_box(corpus))));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_find_matches, bench_find_tokens);
+criterion_main!(benches);
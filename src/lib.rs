@@ -1,302 +1,339 @@
-use lazy_static::lazy_static;
-use onig::Regex;
-
-lazy_static! {
-    // Regex to check if a character is a letter (matches \p{L})
-    static ref LETTER_RE: Regex = Regex::new(r"\A\p{L}\z").unwrap();
-    // Regex to check if a character is a number (matches \p{N})
-    static ref NUMBER_RE: Regex = Regex::new(r"\A\p{N}\z").unwrap();
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+mod patterns;
+mod program;
+mod unicode_tables;
+
+pub use patterns::{Gpt2Pretokenizer, Gpt4Pretokenizer, O200kPretokenizer, Pretokenizer};
+pub use program::{compile, CompileError, Program};
+
+use unicode_tables::{LETTER_RANGES, NUMBER_RANGES};
+
+/// Binary-searches a sorted, inclusive `(start, end)` range table for
+/// membership of `cp`, used as a branchless stand-in for a `\p{L}`/`\p{N}`
+/// regex match.
+fn in_ranges(ranges: &[(u32, u32)], cp: u32) -> bool {
+    ranges
+        .binary_search_by(|&(start, end)| {
+            if cp < start {
+                core::cmp::Ordering::Greater
+            } else if cp > end {
+                core::cmp::Ordering::Less
+            } else {
+                core::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
 }
 
-pub struct PatternIterator<'a> {
-    text: &'a str,
-    current_pos: usize,
+pub(crate) fn is_newline(c: char) -> bool {
+    c == '\r' || c == '\n'
 }
 
-impl<'a> PatternIterator<'a> {
-    pub fn new(text: &'a str) -> Self {
-        Self {
-            text,
-            current_pos: 0,
+pub(crate) fn is_letter(c: char) -> bool {
+    if c.is_ascii() {
+        return c.is_ascii_alphabetic();
+    }
+    in_ranges(LETTER_RANGES, c as u32)
+}
+
+pub(crate) fn is_number(c: char) -> bool {
+    if c.is_ascii() {
+        return c.is_ascii_digit();
+    }
+    in_ranges(NUMBER_RANGES, c as u32)
+}
+
+/// A byte-aware scanning position over a `&str`.
+///
+/// Advancing only ever needs to UTF-8-decode a full `char` for the
+/// `\p{L}`/`\p{N}` checks; the ASCII-dominant cases (spaces, newlines,
+/// digits, the `'[sdmt]` contractions) are decided straight off the raw
+/// bytes of `rest`, since a byte below `0x80` is always a complete
+/// one-byte codepoint on its own (UTF-8 continuation/lead bytes for
+/// multi-byte sequences are always `>= 0x80`).
+#[derive(Clone, Copy)]
+pub(crate) struct Cursor<'a> {
+    rest: &'a str,
+    off: u32,
+}
+
+impl<'a> Cursor<'a> {
+    pub(crate) fn at(text: &'a str, pos: usize) -> Self {
+        Cursor {
+            rest: &text[pos..],
+            off: pos as u32,
         }
     }
 
-    fn is_newline(c: char) -> bool {
-        c == '\r' || c == '\n'
+    pub(crate) fn pos(&self) -> usize {
+        self.off as usize
     }
 
-    fn is_letter(c: char) -> bool {
-        let mut buf = [0u8; 4];
-        let s = c.encode_utf8(&mut buf);
-        LETTER_RE.is_match(s)
+    pub(crate) fn peek_byte(&self) -> Option<u8> {
+        self.rest.as_bytes().first().copied()
     }
 
-    fn is_number(c: char) -> bool {
-        let mut buf = [0u8; 4];
-        let s = c.encode_utf8(&mut buf);
-        NUMBER_RE.is_match(s)
+    /// Advances one byte. Only valid when that byte is `< 0x80`, i.e. it
+    /// is itself a whole one-byte codepoint.
+    pub(crate) fn bump_ascii(&mut self) {
+        self.rest = &self.rest[1..];
+        self.off += 1;
     }
 
-    fn peek_char_at(&self, pos: usize) -> Option<char> {
-        self.text[pos..].chars().next()
+    pub(crate) fn peek_char(&self) -> Option<char> {
+        self.rest.chars().next()
     }
 
-    fn char_len_at(&self, pos: usize) -> usize {
-        self.peek_char_at(pos).map(|c| c.len_utf8()).unwrap_or(0)
+    pub(crate) fn bump_char(&mut self, c: char) {
+        let len = c.len_utf8();
+        self.rest = &self.rest[len..];
+        self.off += len as u32;
     }
 }
 
-impl<'a> Iterator for PatternIterator<'a> {
-    type Item = &'a str;
+fn char_len_at(text: &str, pos: usize) -> usize {
+    Cursor::at(text, pos).peek_char().map(|c| c.len_utf8()).unwrap_or(0)
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
+/// The class of pre-token a match belongs to, tagging *why* the matcher
+/// stopped where it did so downstream BPE code can special-case classes
+/// (e.g. never merge across a `Newline`) without re-scanning the text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// An apostrophe contraction such as `'s`, `'re`, `'ll`.
+    Contraction,
+    /// A run of letters, optionally preceded by a single non-alphanumeric
+    /// lead-in character (e.g. ` hello`). Under [`O200kPretokenizer`] it
+    /// also takes in a directly following contraction (e.g. `don't`).
+    Word,
+    /// A run of digits: 1-3 of them under the GPT-4 and o200k_base
+    /// patterns; under [`Gpt2Pretokenizer`] the run is unbounded and may
+    /// start with a single space.
+    Number,
+    /// A run of non-whitespace, non-alphanumeric characters (optionally
+    /// preceded by a single space and followed by trailing newlines).
+    Punctuation,
+    /// Whitespace that terminates in one or more newline characters.
+    Newline,
+    /// Whitespace that is not classified as `Newline`.
+    Whitespace,
+    /// The single-character fallback used when nothing else matches.
+    Other,
+}
+
+/// A classified pre-token: the original text slice, its `TokenKind`, and
+/// its byte range within the source text, so callers can reconstruct
+/// offsets without re-scanning.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub range: Range<usize>,
+}
+
+const GPT4_PRETOKENIZER: Gpt4Pretokenizer = Gpt4Pretokenizer;
+
+pub struct PatternIterator<'a> {
+    text: &'a str,
+    current_pos: usize,
+    pretokenizer: &'a dyn Pretokenizer,
+}
+
+impl<'a> PatternIterator<'a> {
+    /// Tokenizes with the default (GPT-4 / cl100k_base) pattern.
+    pub fn new(text: &'a str) -> Self {
+        Self::with_pretokenizer(text, &GPT4_PRETOKENIZER)
+    }
+
+    /// Tokenizes using a caller-supplied [`Pretokenizer`], e.g. [`Gpt2Pretokenizer`]
+    /// or [`O200kPretokenizer`].
+    pub fn with_pretokenizer(text: &'a str, pretokenizer: &'a dyn Pretokenizer) -> Self {
+        Self {
+            text,
+            current_pos: 0,
+            pretokenizer,
+        }
+    }
+
+    /// Asks the configured [`Pretokenizer`] for the next match and
+    /// advances `current_pos` past it, falling back to a single-character
+    /// token when nothing in the pattern matches there.
+    fn advance(&mut self) -> Option<Token<'a>> {
         if self.current_pos >= self.text.len() {
             return None;
         }
 
         let start_pos = self.current_pos;
-        
-        if let Some(end_pos) = self.try_match_apostrophe_contractions(start_pos) {
-            self.current_pos = end_pos;
-            return Some(&self.text[start_pos..end_pos]);
-        }
-        
-        if let Some(end_pos) = self.try_match_optional_nonalpha_plus_letters(start_pos) {
-            self.current_pos = end_pos;
-            return Some(&self.text[start_pos..end_pos]);
-        }
-        
-        if let Some(end_pos) = self.try_match_numbers_1_to_3(start_pos) {
-            self.current_pos = end_pos;
-            return Some(&self.text[start_pos..end_pos]);
-        }
-        
-        if let Some(end_pos) = self.try_match_space_plus_nonwhitespace_with_newlines(start_pos) {
-            self.current_pos = end_pos;
-            return Some(&self.text[start_pos..end_pos]);
-        }
-        
-        if let Some(end_pos) = self.try_match_whitespace_before_newlines(start_pos) {
-            self.current_pos = end_pos;
-            return Some(&self.text[start_pos..end_pos]);
-        }
-        
-        if let Some(end_pos) = self.try_match_whitespace_followed_by_whitespace_or_end(start_pos) {
-            self.current_pos = end_pos;
-            return Some(&self.text[start_pos..end_pos]);
-        }
-        
-        if let Some(end_pos) = self.try_match_any_whitespace(start_pos) {
-            self.current_pos = end_pos;
-            return Some(&self.text[start_pos..end_pos]);
-        }
 
-        self.current_pos = start_pos + self.char_len_at(start_pos).max(1);
-        Some(&self.text[start_pos..self.current_pos])
+        let (len, kind) = self
+            .pretokenizer
+            .next_match(self.text, start_pos)
+            .unwrap_or_else(|| (char_len_at(self.text, start_pos).max(1), TokenKind::Other));
+
+        let end_pos = start_pos + len;
+        self.current_pos = end_pos;
+        Some(Token {
+            kind,
+            text: &self.text[start_pos..end_pos],
+            range: start_pos..end_pos,
+        })
     }
 }
 
-impl<'a> PatternIterator<'a> {
-    fn try_match_apostrophe_contractions(&self, start_pos: usize) -> Option<usize> {
-        if start_pos >= self.text.len() || !self.text[start_pos..].starts_with('\'') {
-            return None;
-        }
+impl<'a> Iterator for PatternIterator<'a> {
+    type Item = &'a str;
 
-        let rest = &self.text[start_pos + 1..];
-        let mut chars = rest.chars();
-        
-        if let Some(first_char) = chars.next() {
-            if let Some(second_char) = chars.next() {
-                let two_char_str = format!("{}{}", first_char, second_char);
-                if two_char_str.eq_ignore_ascii_case("ll") || 
-                   two_char_str.eq_ignore_ascii_case("ve") || 
-                   two_char_str.eq_ignore_ascii_case("re") {
-                    return Some(start_pos + 1 + first_char.len_utf8() + second_char.len_utf8());
-                }
-            }
-            
-            if first_char.to_ascii_lowercase() == 's' || 
-               first_char.to_ascii_lowercase() == 'd' || 
-               first_char.to_ascii_lowercase() == 'm' || 
-               first_char.to_ascii_lowercase() == 't' {
-                return Some(start_pos + 1 + first_char.len_utf8());
-            }
-        }
-        
-        None
+    fn next(&mut self) -> Option<Self::Item> {
+        self.advance().map(|token| token.text)
     }
+}
 
-    fn try_match_optional_nonalpha_plus_letters(&self, start_pos: usize) -> Option<usize> {
-        let mut pos = start_pos;
+/// Like [`PatternIterator`], but yields the classified [`Token`] for each
+/// match instead of discarding its [`TokenKind`].
+pub struct TokenIterator<'a> {
+    inner: PatternIterator<'a>,
+}
 
-        // Optional non-alphabetic, non-numeric, non-newline character
-        if let Some(c) = self.peek_char_at(pos) {
-            if !Self::is_letter(c) && !Self::is_number(c) && !Self::is_newline(c) {
-                pos += c.len_utf8();
-            }
+impl<'a> TokenIterator<'a> {
+    /// Tokenizes with the default (GPT-4 / cl100k_base) pattern.
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            inner: PatternIterator::new(text),
         }
+    }
 
-        // Must be followed by one or more alphabetic characters
-        let letter_start = pos;
-        while let Some(c) = self.peek_char_at(pos) {
-            if Self::is_letter(c) {
-                pos += c.len_utf8();
-            } else {
-                break;
-            }
+    /// Tokenizes using a caller-supplied [`Pretokenizer`].
+    pub fn with_pretokenizer(text: &'a str, pretokenizer: &'a dyn Pretokenizer) -> Self {
+        Self {
+            inner: PatternIterator::with_pretokenizer(text, pretokenizer),
         }
+    }
+}
 
-        // We need at least one letter after the optional non-alpha character
-        if pos > letter_start {
-            Some(pos)
-        } else {
-            None
-        }
+impl<'a> Iterator for TokenIterator<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.advance()
     }
+}
 
-    fn try_match_numbers_1_to_3(&self, start_pos: usize) -> Option<usize> {
-        let mut pos = start_pos;
-        let mut count = 0;
-        
-        while count < 3 {
-            if let Some(c) = self.peek_char_at(pos) {
-                if Self::is_number(c) {
-                    pos += c.len_utf8();
-                    count += 1;
-                } else {
-                    break;
-                }
-            } else {
-                break;
-            }
-        }
-        
-        if count > 0 {
-            Some(pos)
-        } else {
-            None
-        }
+pub fn find_matches(text: &str) -> Vec<&str> {
+    PatternIterator::new(text).collect()
+}
+
+/// Like [`find_matches`], but returns the classified [`Token`] stream
+/// instead of bare slices.
+pub fn find_tokens(text: &str) -> Vec<Token<'_>> {
+    TokenIterator::new(text).collect()
+}
+
+/// Like [`find_matches`], but tokenizes with a caller-supplied [`Pretokenizer`]
+/// instead of the default GPT-4 / cl100k_base pattern.
+pub fn find_matches_with<'a>(text: &'a str, pretokenizer: &'a dyn Pretokenizer) -> Vec<&'a str> {
+    PatternIterator::with_pretokenizer(text, pretokenizer).collect()
+}
+
+/// Like [`find_tokens`], but tokenizes with a caller-supplied [`Pretokenizer`].
+pub fn find_tokens_with<'a>(text: &'a str, pretokenizer: &'a dyn Pretokenizer) -> Vec<Token<'a>> {
+    TokenIterator::with_pretokenizer(text, pretokenizer).collect()
+}
+
+/// A stateful, push-based tokenizer for text that arrives in arbitrary
+/// chunks (e.g. from a file reader or socket).
+///
+/// [`feed`](Self::feed) only emits pre-tokens it can prove are complete:
+/// a run that could still be extended by the next chunk (an unterminated
+/// run of letters, digits, whitespace, or punctuation sitting right at the
+/// end of the buffered input) is held back until more input arrives or
+/// [`finish`](Self::finish) flushes it. This lets callers tokenize
+/// multi-gigabyte inputs without materializing the whole string.
+pub struct StreamTokenizer<'p> {
+    /// Input fed so far that has not yet been proven complete.
+    buffer: String,
+    /// The prefix of `buffer` that was proven complete by the last
+    /// `feed`/`finish` call, kept around so the returned iterator can
+    /// borrow from it.
+    ready: String,
+    pretokenizer: &'p dyn Pretokenizer,
+}
+
+impl StreamTokenizer<'static> {
+    /// Streams with the default (GPT-4 / cl100k_base) pattern.
+    pub fn new() -> Self {
+        Self::with_pretokenizer(&GPT4_PRETOKENIZER)
     }
+}
 
-    fn try_match_space_plus_nonwhitespace_with_newlines(&self, start_pos: usize) -> Option<usize> {
-        if start_pos >= self.text.len() {
-            return None;
-        }
-        
-        let mut pos = start_pos;
-        
-        if let Some(c) = self.peek_char_at(pos) {
-            if c == ' ' {
-                pos += c.len_utf8();
-            }
-        }
-        
-        let special_start = pos;
-        while let Some(c) = self.peek_char_at(pos) {
-            if !c.is_whitespace() && !Self::is_letter(c) && !Self::is_number(c) {
-                pos += c.len_utf8();
-            } else {
-                break;
-            }
-        }
-        
-        if pos > special_start {
-            while let Some(c) = self.peek_char_at(pos) {
-                if Self::is_newline(c) {
-                    pos += c.len_utf8();
-                } else {
-                    break;
-                }
-            }
-            Some(pos)
-        } else {
-            None
-        }
+impl Default for StreamTokenizer<'static> {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    fn try_match_whitespace_before_newlines(&self, start_pos: usize) -> Option<usize> {
-        let mut pos = start_pos;
-        
-        while let Some(c) = self.peek_char_at(pos) {
-            if c.is_whitespace() && !Self::is_newline(c) {
-                pos += c.len_utf8();
-            } else {
-                break;
-            }
-        }
-        
-        let newline_start = pos;
-        while let Some(c) = self.peek_char_at(pos) {
-            if Self::is_newline(c) {
-                pos += c.len_utf8();
-            } else {
-                break;
-            }
-        }
-        
-        if pos > newline_start {
-            Some(pos)
-        } else {
-            None
+impl<'p> StreamTokenizer<'p> {
+    /// Streams using a caller-supplied [`Pretokenizer`], e.g.
+    /// [`Gpt2Pretokenizer`] or [`O200kPretokenizer`].
+    pub fn with_pretokenizer(pretokenizer: &'p dyn Pretokenizer) -> Self {
+        Self {
+            buffer: String::new(),
+            ready: String::new(),
+            pretokenizer,
         }
     }
 
-    fn try_match_whitespace_followed_by_whitespace_or_end(&self, start_pos: usize) -> Option<usize> {
-        if start_pos >= self.text.len() {
-            return None;
-        }
-        
-        if let Some(c) = self.peek_char_at(start_pos) {
-            if !c.is_whitespace() || Self::is_newline(c) {
-                return None;
-            }
-        }
-        
-        let mut positions = Vec::new();
-        let mut pos = start_pos;
-        
-        while let Some(c) = self.peek_char_at(pos) {
-            if c.is_whitespace() && !Self::is_newline(c) {
-                pos += c.len_utf8();
-                positions.push(pos);
-            } else {
-                break;
-            }
-        }
-        
-        for &end_pos in positions.iter().rev() {
-            match self.peek_char_at(end_pos) {
-                None => return Some(end_pos),
-                Some(c) if c.is_whitespace() => return Some(end_pos),
-                Some(_) => continue,
-            }
-        }
-        
-        None
+    /// Feeds the next chunk of input and returns the tokens that are now
+    /// provably complete. Any trailing run that might still be extended
+    /// by a future chunk is buffered internally instead of being yielded.
+    pub fn feed<'a>(&'a mut self, chunk: &str) -> impl Iterator<Item = Token<'a>> + 'a {
+        self.buffer.push_str(chunk);
+        let boundary = self.safe_boundary(&self.buffer);
+
+        self.ready.clear();
+        self.ready.push_str(&self.buffer[..boundary]);
+        self.buffer.drain(..boundary);
+
+        TokenIterator::with_pretokenizer(self.ready.as_str(), self.pretokenizer)
     }
 
-    fn try_match_any_whitespace(&self, start_pos: usize) -> Option<usize> {
-        let mut pos = start_pos;
-        let ws_start = pos;
-        
-        while let Some(c) = self.peek_char_at(pos) {
-            if c.is_whitespace() {
-                pos += c.len_utf8();
-            } else {
-                break;
+    /// Flushes whatever input remains buffered, treating it as the end of
+    /// the stream. After this call the tokenizer is empty again.
+    pub fn finish(&mut self) -> impl Iterator<Item = Token<'_>> {
+        self.ready.clear();
+        self.ready.push_str(&self.buffer);
+        self.buffer.clear();
+
+        TokenIterator::with_pretokenizer(self.ready.as_str(), self.pretokenizer)
+    }
+
+    /// Returns the length of the longest prefix of `text` whose tokens are
+    /// guaranteed not to change no matter what text follows.
+    ///
+    /// An unfinished last token also holds back the token before it, since
+    /// more input can merge the two: GPT-2 splits `'r` into `'` + `r` until
+    /// an `e` makes it the contraction `'re`, and o200k_base's `don` takes
+    /// in a `'t` that arrives later.
+    fn safe_boundary(&self, text: &str) -> usize {
+        let mut boundary = 0;
+        let mut prev_start = 0;
+
+        for token in TokenIterator::with_pretokenizer(text, self.pretokenizer) {
+            if token.range.end == text.len() && !self.pretokenizer.is_final(token.kind, token.text) {
+                return prev_start;
             }
+            prev_start = token.range.start;
+            boundary = token.range.end;
         }
-        
-        if pos > ws_start {
-            Some(pos)
-        } else {
-            None
-        }
-    }
-}
 
-pub fn find_matches(text: &str) -> Vec<&str> {
-    PatternIterator::new(text).collect()
+        boundary
+    }
 }
 
 #[cfg(test)]
@@ -365,4 +402,129 @@ mod tests {
             assert_eq!(regex_result, library_result, "Mismatch found for input: {:?}", s);
         }
     }
+
+    #[test]
+    fn test_tokens_agree_with_find_matches() {
+        let input = "Hello, world! It's 123 days.\nNext line.";
+        let tokens = find_tokens(input);
+        let slices: Vec<&str> = tokens.iter().map(|t| t.text).collect();
+
+        assert_eq!(slices, find_matches(input));
+        for token in &tokens {
+            assert_eq!(&input[token.range.clone()], token.text);
+        }
+    }
+
+    #[test]
+    fn test_token_kinds() {
+        let tokens = find_tokens("It's 7");
+        let kinds: Vec<TokenKind> = tokens.iter().map(|t| t.kind).collect();
+
+        assert_eq!(
+            kinds,
+            vec![TokenKind::Word, TokenKind::Contraction, TokenKind::Whitespace, TokenKind::Number]
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn proptest_tokens_match_find_matches(s in "\\PC*") {
+            let library_result = find_matches(&s);
+            let token_result: Vec<&str> = find_tokens(&s).iter().map(|t| t.text).collect();
+
+            assert_eq!(library_result, token_result, "Mismatch found for input: {:?}", s);
+        }
+    }
+
+    fn feed_in_chunks(text: &str, chunk_size: usize) -> Vec<String> {
+        let mut tokenizer = StreamTokenizer::new();
+        let mut texts = Vec::new();
+
+        let mut rest = text;
+        while !rest.is_empty() {
+            let split_at = rest
+                .char_indices()
+                .nth(chunk_size)
+                .map(|(i, _)| i)
+                .unwrap_or(rest.len());
+            let (chunk, remainder) = rest.split_at(split_at);
+            rest = remainder;
+            texts.extend(tokenizer.feed(chunk).map(|t| t.text.to_string()));
+        }
+        texts.extend(tokenizer.finish().map(|t| t.text.to_string()));
+        texts
+    }
+
+    #[test]
+    fn test_stream_tokenizer_matches_find_matches() {
+        let input = "Hello, world! It's 12345 days.\nNext  line.";
+        let expected: Vec<&str> = find_matches(input);
+
+        for chunk_size in 1..=8 {
+            let streamed = feed_in_chunks(input, chunk_size);
+            assert_eq!(streamed, expected, "chunk_size={}", chunk_size);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn proptest_stream_tokenizer_matches_find_matches(s in "\\PC*", chunk_size in 1usize..6) {
+            let expected = find_matches(&s);
+            let streamed = feed_in_chunks(&s, chunk_size);
+
+            assert_eq!(streamed, expected, "Mismatch found for input: {:?}", s);
+        }
+    }
+
+    #[test]
+    fn test_stream_tokenizer_with_pretokenizer_keeps_unbounded_number_buffered() {
+        // GPT-4's `\p{N}{1,3}` caps digit runs at 3 characters, so under the
+        // default pretokenizer "123" is already final and gets flushed
+        // immediately. GPT-2's number runs are unbounded, so the same chunk
+        // must stay buffered in case the next chunk extends it with more
+        // digits.
+        let mut gpt4 = StreamTokenizer::new();
+        assert_eq!(
+            gpt4.feed("123").map(|t| t.text.to_string()).collect::<Vec<_>>(),
+            vec!["123"]
+        );
+
+        let mut gpt2 = StreamTokenizer::with_pretokenizer(&Gpt2Pretokenizer);
+        assert!(gpt2.feed("123").next().is_none());
+        assert_eq!(
+            gpt2.feed("45").map(|t| t.text.to_string()).collect::<Vec<_>>(),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            gpt2.finish().map(|t| t.text.to_string()).collect::<Vec<_>>(),
+            vec!["12345"]
+        );
+    }
+
+    #[test]
+    fn test_stream_tokenizer_with_pretokenizer_matches_find_tokens_with() {
+        let input = "Hello, world! It's 12345 days.\nNext  line.";
+        let expected: Vec<String> = find_tokens_with(input, &Gpt2Pretokenizer)
+            .into_iter()
+            .map(|t| t.text.to_string())
+            .collect();
+
+        for chunk_size in 1..=8 {
+            let mut tokenizer = StreamTokenizer::with_pretokenizer(&Gpt2Pretokenizer);
+            let mut texts = Vec::new();
+            let mut rest = input;
+            while !rest.is_empty() {
+                let split_at = rest
+                    .char_indices()
+                    .nth(chunk_size)
+                    .map(|(i, _)| i)
+                    .unwrap_or(rest.len());
+                let (chunk, remainder) = rest.split_at(split_at);
+                rest = remainder;
+                texts.extend(tokenizer.feed(chunk).map(|t| t.text.to_string()));
+            }
+            texts.extend(tokenizer.finish().map(|t| t.text.to_string()));
+            assert_eq!(texts, expected, "chunk_size={}", chunk_size);
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,568 @@
+//! Pluggable pre-tokenization rule sets.
+//!
+//! [`PatternIterator`](crate::PatternIterator) drives any [`Pretokenizer`]
+//! it's given instead of being wired to a single hand-coded pattern. Each
+//! implementation here mirrors one of the splitting patterns real BPE
+//! tokenizers use, trying its alternatives in the same priority order as
+//! the reference regex and reporting the first one that matches.
+
+use alloc::vec::Vec;
+
+use crate::{is_letter, is_newline, is_number, Cursor, TokenKind};
+
+/// A pre-tokenization rule set: given `text` and a byte offset `pos`,
+/// tries this pattern's alternatives in priority order and reports the
+/// byte length and [`TokenKind`] of whichever one matched, or `None` if
+/// none of them match at `pos`.
+///
+/// [`PatternIterator`](crate::PatternIterator) falls back to a
+/// single-character [`TokenKind::Other`] token whenever a `Pretokenizer`
+/// returns `None`, so implementations don't need to handle that case
+/// themselves.
+pub trait Pretokenizer {
+    fn next_match(&self, text: &str, pos: usize) -> Option<(usize, TokenKind)>;
+
+    /// Whether a match of `kind` with text `text`, sitting right at the
+    /// end of a [`StreamTokenizer`](crate::StreamTokenizer)'s buffered
+    /// input, is guaranteed complete no matter what text arrives next.
+    ///
+    /// The default matches GPT-4/o200k_base, whose `\p{N}{1,3}` caps
+    /// digit runs at 3 characters: `Contraction` and `Other` are already
+    /// maximal by construction, `Number` is final once it hits the cap,
+    /// and every other kind matches an unbounded run that could still be
+    /// extended. Pattern families with different caps (or none) should
+    /// override this; see [`Gpt2Pretokenizer`], whose number/word/
+    /// punctuation/whitespace runs are all unbounded.
+    fn is_final(&self, kind: TokenKind, text: &str) -> bool {
+        match kind {
+            TokenKind::Contraction | TokenKind::Other => true,
+            TokenKind::Number => text.chars().count() >= 3,
+            TokenKind::Word | TokenKind::Whitespace | TokenKind::Newline | TokenKind::Punctuation => false,
+        }
+    }
+}
+
+/// The hand-written GPT-4 / `cl100k_base` splitting pattern:
+///
+/// ```text
+/// '(?i:[sdmt]|ll|ve|re)|[^\r\n\p{L}\p{N}]?+\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]++[\r\n]*|\s*[\r\n]|\s+(?!\S)|\s+
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Gpt4Pretokenizer;
+
+impl Pretokenizer for Gpt4Pretokenizer {
+    fn next_match(&self, text: &str, pos: usize) -> Option<(usize, TokenKind)> {
+        if let Some(end) = try_match_apostrophe_contractions(text, pos) {
+            return Some((end - pos, TokenKind::Contraction));
+        }
+        if let Some(end) = try_match_optional_nonalpha_plus_letters(text, pos) {
+            return Some((end - pos, TokenKind::Word));
+        }
+        if let Some(end) = try_match_numbers_1_to_3(text, pos) {
+            return Some((end - pos, TokenKind::Number));
+        }
+        if let Some(end) = try_match_space_plus_nonwhitespace_with_newlines(text, pos) {
+            return Some((end - pos, TokenKind::Punctuation));
+        }
+        if let Some(end) = try_match_whitespace_before_newlines(text, pos) {
+            return Some((end - pos, TokenKind::Newline));
+        }
+        if let Some(end) = try_match_whitespace_followed_by_whitespace_or_end(text, pos) {
+            return Some((end - pos, TokenKind::Whitespace));
+        }
+        if let Some(end) = try_match_any_whitespace(text, pos) {
+            return Some((end - pos, TokenKind::Whitespace));
+        }
+        None
+    }
+}
+
+/// The older GPT-2 splitting pattern:
+///
+/// ```text
+/// 's|'t|'re|'ve|'m|'ll|'d| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+(?!\S)|\s+
+/// ```
+///
+/// Unlike GPT-4's pattern, the letter/number/punctuation runs here are
+/// unbounded (no `{1,3}` cap on digits) and their optional lead-in is a
+/// single literal space rather than any non-alphanumeric character.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Gpt2Pretokenizer;
+
+impl Pretokenizer for Gpt2Pretokenizer {
+    fn next_match(&self, text: &str, pos: usize) -> Option<(usize, TokenKind)> {
+        if let Some(end) = try_match_case_sensitive_apostrophe_contractions(text, pos) {
+            return Some((end - pos, TokenKind::Contraction));
+        }
+        if let Some(end) = try_match_optional_space_plus_letters(text, pos) {
+            return Some((end - pos, TokenKind::Word));
+        }
+        if let Some(end) = try_match_optional_space_plus_numbers(text, pos) {
+            return Some((end - pos, TokenKind::Number));
+        }
+        if let Some(end) = try_match_optional_space_plus_punctuation(text, pos) {
+            return Some((end - pos, TokenKind::Punctuation));
+        }
+        if let Some(end) = try_match_any_whitespace_followed_by_whitespace_or_end(text, pos) {
+            return Some((end - pos, whitespace_kind(text, pos, end)));
+        }
+        if let Some(end) = try_match_any_whitespace(text, pos) {
+            return Some((end - pos, whitespace_kind(text, pos, end)));
+        }
+        None
+    }
+
+    /// Unlike GPT-4/o200k_base, GPT-2's pattern has no `{1,3}` cap on any
+    /// alternative: `Number`, `Word`, `Punctuation`, and `Whitespace` runs
+    /// are all unbounded, so only `Contraction`/`Other` are ever final.
+    fn is_final(&self, kind: TokenKind, _text: &str) -> bool {
+        matches!(kind, TokenKind::Contraction | TokenKind::Other)
+    }
+}
+
+/// A reduced model of the newer o200k_base pattern used by GPT-4o, which
+/// (unlike GPT-4's pattern) lets a word run absorb an immediately
+/// following case-insensitive contraction as part of the same token,
+/// while keeping GPT-4's `{1,3}`-capped number groups.
+///
+/// The real o200k_base regex additionally splits word runs on
+/// upper/lowercase transitions (`\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}*` vs.
+/// `\p{Ll}\p{Lm}\p{Lo}\p{M}*`), which this reduced model doesn't
+/// reproduce; it's close enough for pre-tokenizing ordinary text but
+/// will diverge from `tiktoken` on camel-cased runs.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct O200kPretokenizer;
+
+impl Pretokenizer for O200kPretokenizer {
+    fn next_match(&self, text: &str, pos: usize) -> Option<(usize, TokenKind)> {
+        if let Some(end) = try_match_word_with_trailing_contraction(text, pos) {
+            return Some((end - pos, TokenKind::Word));
+        }
+        if let Some(end) = try_match_numbers_1_to_3(text, pos) {
+            return Some((end - pos, TokenKind::Number));
+        }
+        if let Some(end) = try_match_space_plus_nonwhitespace_with_newlines(text, pos) {
+            return Some((end - pos, TokenKind::Punctuation));
+        }
+        if let Some(end) = try_match_whitespace_before_newlines(text, pos) {
+            return Some((end - pos, TokenKind::Newline));
+        }
+        if let Some(end) = try_match_whitespace_followed_by_whitespace_or_end(text, pos) {
+            return Some((end - pos, TokenKind::Whitespace));
+        }
+        if let Some(end) = try_match_any_whitespace(text, pos) {
+            return Some((end - pos, TokenKind::Whitespace));
+        }
+        None
+    }
+}
+
+/// `TokenKind::Newline` if the matched whitespace run ends in a newline,
+/// `TokenKind::Whitespace` otherwise. Used by patterns (like GPT-2's)
+/// whose whitespace alternatives don't separate newlines out up front the
+/// way GPT-4's pattern does.
+fn whitespace_kind(text: &str, start: usize, end: usize) -> TokenKind {
+    match text[start..end].chars().next_back() {
+        Some(c) if is_newline(c) => TokenKind::Newline,
+        _ => TokenKind::Whitespace,
+    }
+}
+
+/// Matches GPT-4's `(?i:[sdmt]|ll|ve|re)` contraction group, which is
+/// wrapped in a case-insensitive flag, so `'M` and `'m` both count.
+fn try_match_apostrophe_contractions(text: &str, start_pos: usize) -> Option<usize> {
+    let bytes = text.as_bytes();
+    if bytes.get(start_pos) != Some(&b'\'') {
+        return None;
+    }
+
+    if let (Some(&b1), Some(&b2)) = (bytes.get(start_pos + 1), bytes.get(start_pos + 2)) {
+        let two = [b1.to_ascii_lowercase(), b2.to_ascii_lowercase()];
+        if matches!(&two, b"ll" | b"ve" | b"re") {
+            return Some(start_pos + 3);
+        }
+    }
+
+    if let Some(&b1) = bytes.get(start_pos + 1) {
+        if matches!(b1.to_ascii_lowercase(), b's' | b'd' | b'm' | b't') {
+            return Some(start_pos + 2);
+        }
+    }
+
+    None
+}
+
+/// Matches GPT-2's `'s|'t|'re|'ve|'m|'ll|'d` contraction alternation,
+/// which (unlike GPT-4's) has no `(?i:...)` wrapper, so `'M` in "I'M" is
+/// not a contraction and falls through to punctuation matching instead.
+fn try_match_case_sensitive_apostrophe_contractions(text: &str, start_pos: usize) -> Option<usize> {
+    let bytes = text.as_bytes();
+    if bytes.get(start_pos) != Some(&b'\'') {
+        return None;
+    }
+
+    if let (Some(&b1), Some(&b2)) = (bytes.get(start_pos + 1), bytes.get(start_pos + 2)) {
+        if matches!(&[b1, b2], b"ll" | b"ve" | b"re") {
+            return Some(start_pos + 3);
+        }
+    }
+
+    if let Some(&b1) = bytes.get(start_pos + 1) {
+        if matches!(b1, b's' | b'd' | b'm' | b't') {
+            return Some(start_pos + 2);
+        }
+    }
+
+    None
+}
+
+fn try_match_optional_nonalpha_plus_letters(text: &str, start_pos: usize) -> Option<usize> {
+    let mut cur = Cursor::at(text, start_pos);
+
+    // Optional non-alphabetic, non-numeric, non-newline character
+    if let Some(c) = cur.peek_char() {
+        if !is_letter(c) && !is_number(c) && !is_newline(c) {
+            cur.bump_char(c);
+        }
+    }
+
+    // Must be followed by one or more alphabetic characters
+    let letter_start = cur.pos();
+    while let Some(c) = cur.peek_char() {
+        if is_letter(c) {
+            cur.bump_char(c);
+        } else {
+            break;
+        }
+    }
+
+    if cur.pos() > letter_start {
+        Some(cur.pos())
+    } else {
+        None
+    }
+}
+
+fn try_match_word_with_trailing_contraction(text: &str, start_pos: usize) -> Option<usize> {
+    let word_end = try_match_optional_nonalpha_plus_letters(text, start_pos)?;
+    Some(try_match_apostrophe_contractions(text, word_end).unwrap_or(word_end))
+}
+
+fn try_match_numbers_1_to_3(text: &str, start_pos: usize) -> Option<usize> {
+    let mut cur = Cursor::at(text, start_pos);
+    let mut count = 0;
+
+    while count < 3 {
+        match cur.peek_byte() {
+            Some(b) if b.is_ascii_digit() => {
+                cur.bump_ascii();
+                count += 1;
+            }
+            Some(b) if b < 0x80 => break,
+            Some(_) => match cur.peek_char() {
+                Some(c) if is_number(c) => {
+                    cur.bump_char(c);
+                    count += 1;
+                }
+                _ => break,
+            },
+            None => break,
+        }
+    }
+
+    if count > 0 {
+        Some(cur.pos())
+    } else {
+        None
+    }
+}
+
+fn try_match_space_plus_nonwhitespace_with_newlines(text: &str, start_pos: usize) -> Option<usize> {
+    if start_pos >= text.len() {
+        return None;
+    }
+
+    let mut cur = Cursor::at(text, start_pos);
+
+    if cur.peek_byte() == Some(b' ') {
+        cur.bump_ascii();
+    }
+
+    let special_start = cur.pos();
+    while let Some(c) = cur.peek_char() {
+        if !c.is_whitespace() && !is_letter(c) && !is_number(c) {
+            cur.bump_char(c);
+        } else {
+            break;
+        }
+    }
+
+    if cur.pos() > special_start {
+        while let Some(b) = cur.peek_byte() {
+            if b == b'\r' || b == b'\n' {
+                cur.bump_ascii();
+            } else {
+                break;
+            }
+        }
+        Some(cur.pos())
+    } else {
+        None
+    }
+}
+
+/// `\s*[\r\n]`: the greedy `\s*` takes the whole whitespace run (newlines
+/// included) and gives characters back until a newline is left for
+/// `[\r\n]`, so the match ends right after the run's last newline.
+fn try_match_whitespace_before_newlines(text: &str, start_pos: usize) -> Option<usize> {
+    let mut cur = Cursor::at(text, start_pos);
+    let mut end = None;
+
+    loop {
+        match cur.peek_byte() {
+            Some(b) if b == b'\r' || b == b'\n' => {
+                cur.bump_ascii();
+                end = Some(cur.pos());
+            }
+            Some(b) if b.is_ascii_whitespace() || b == 0x0B => cur.bump_ascii(),
+            Some(b) if b < 0x80 => break,
+            Some(_) => match cur.peek_char() {
+                Some(c) if c.is_whitespace() => cur.bump_char(c),
+                _ => break,
+            },
+            None => break,
+        }
+    }
+
+    end
+}
+
+fn try_match_whitespace_followed_by_whitespace_or_end(text: &str, start_pos: usize) -> Option<usize> {
+    if start_pos >= text.len() {
+        return None;
+    }
+
+    let mut cur = Cursor::at(text, start_pos);
+
+    match cur.peek_char() {
+        Some(c) if c.is_whitespace() && !is_newline(c) => {}
+        _ => return None,
+    }
+
+    let mut positions = Vec::new();
+
+    loop {
+        match cur.peek_byte() {
+            Some(b) if (b.is_ascii_whitespace() || b == 0x0B) && b != b'\r' && b != b'\n' => {
+                cur.bump_ascii();
+                positions.push(cur.pos());
+            }
+            Some(b) if b < 0x80 => break,
+            Some(_) => match cur.peek_char() {
+                Some(c) if c.is_whitespace() && !is_newline(c) => {
+                    cur.bump_char(c);
+                    positions.push(cur.pos());
+                }
+                _ => break,
+            },
+            None => break,
+        }
+    }
+
+    for &end_pos in positions.iter().rev() {
+        match Cursor::at(text, end_pos).peek_char() {
+            None => return Some(end_pos),
+            Some(c) if c.is_whitespace() => return Some(end_pos),
+            Some(_) => continue,
+        }
+    }
+
+    None
+}
+
+/// Like [`try_match_whitespace_followed_by_whitespace_or_end`], but
+/// (matching GPT-2's plain `\s+(?!\S)`) doesn't carve newlines out of the
+/// run first.
+fn try_match_any_whitespace_followed_by_whitespace_or_end(text: &str, start_pos: usize) -> Option<usize> {
+    if start_pos >= text.len() {
+        return None;
+    }
+
+    let mut cur = Cursor::at(text, start_pos);
+
+    match cur.peek_char() {
+        Some(c) if c.is_whitespace() => {}
+        _ => return None,
+    }
+
+    let mut positions = Vec::new();
+
+    loop {
+        match cur.peek_byte() {
+            Some(b) if b.is_ascii_whitespace() || b == 0x0B => {
+                cur.bump_ascii();
+                positions.push(cur.pos());
+            }
+            Some(b) if b < 0x80 => break,
+            Some(_) => match cur.peek_char() {
+                Some(c) if c.is_whitespace() => {
+                    cur.bump_char(c);
+                    positions.push(cur.pos());
+                }
+                _ => break,
+            },
+            None => break,
+        }
+    }
+
+    for &end_pos in positions.iter().rev() {
+        match Cursor::at(text, end_pos).peek_char() {
+            None => return Some(end_pos),
+            Some(c) if c.is_whitespace() => return Some(end_pos),
+            Some(_) => continue,
+        }
+    }
+
+    None
+}
+
+fn try_match_any_whitespace(text: &str, start_pos: usize) -> Option<usize> {
+    let mut cur = Cursor::at(text, start_pos);
+    let ws_start = cur.pos();
+
+    loop {
+        match cur.peek_byte() {
+            Some(b) if b.is_ascii_whitespace() || b == 0x0B => cur.bump_ascii(),
+            Some(b) if b < 0x80 => break,
+            Some(_) => match cur.peek_char() {
+                Some(c) if c.is_whitespace() => cur.bump_char(c),
+                _ => break,
+            },
+            None => break,
+        }
+    }
+
+    if cur.pos() > ws_start {
+        Some(cur.pos())
+    } else {
+        None
+    }
+}
+
+fn try_match_optional_space_plus_letters(text: &str, start_pos: usize) -> Option<usize> {
+    let mut cur = Cursor::at(text, start_pos);
+
+    if cur.peek_byte() == Some(b' ') {
+        cur.bump_ascii();
+    }
+
+    let letter_start = cur.pos();
+    while let Some(c) = cur.peek_char() {
+        if is_letter(c) {
+            cur.bump_char(c);
+        } else {
+            break;
+        }
+    }
+
+    if cur.pos() > letter_start {
+        Some(cur.pos())
+    } else {
+        None
+    }
+}
+
+fn try_match_optional_space_plus_numbers(text: &str, start_pos: usize) -> Option<usize> {
+    let mut cur = Cursor::at(text, start_pos);
+
+    if cur.peek_byte() == Some(b' ') {
+        cur.bump_ascii();
+    }
+
+    let number_start = cur.pos();
+    while let Some(c) = cur.peek_char() {
+        if is_number(c) {
+            cur.bump_char(c);
+        } else {
+            break;
+        }
+    }
+
+    if cur.pos() > number_start {
+        Some(cur.pos())
+    } else {
+        None
+    }
+}
+
+fn try_match_optional_space_plus_punctuation(text: &str, start_pos: usize) -> Option<usize> {
+    let mut cur = Cursor::at(text, start_pos);
+
+    if cur.peek_byte() == Some(b' ') {
+        cur.bump_ascii();
+    }
+
+    let punct_start = cur.pos();
+    while let Some(c) = cur.peek_char() {
+        if !c.is_whitespace() && !is_letter(c) && !is_number(c) {
+            cur.bump_char(c);
+        } else {
+            break;
+        }
+    }
+
+    if cur.pos() > punct_start {
+        Some(cur.pos())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find_matches_with, find_tokens_with};
+    use onig::Regex;
+    use proptest::prelude::*;
+
+    const GPT2_PATTERN: &str = r"'s|'t|'re|'ve|'m|'ll|'d| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+(?!\S)|\s+";
+
+    fn run_regex<'a>(pattern: &str, text: &'a str) -> Vec<&'a str> {
+        let re = Regex::new(pattern).unwrap();
+        re.find_iter(text).map(|(start, end)| &text[start..end]).collect()
+    }
+
+    #[test]
+    fn test_gpt2_matches_reference_regex() {
+        let input = "It's 12345 days since café's grand opening!\n\nNext  line.";
+        let regex_result = run_regex(GPT2_PATTERN, input);
+        let library_result = find_matches_with(input, &Gpt2Pretokenizer);
+
+        assert_eq!(regex_result, library_result);
+    }
+
+    #[test]
+    fn test_o200k_word_absorbs_trailing_contraction() {
+        let tokens = find_tokens_with("don't", &O200kPretokenizer);
+        let texts: Vec<&str> = tokens.iter().map(|t| t.text).collect();
+
+        assert_eq!(texts, vec!["don't"]);
+        assert_eq!(tokens[0].kind, TokenKind::Word);
+    }
+
+    #[test]
+    fn test_pretokenizers_agree_with_gpt4_default() {
+        let input = "Hello, world! It's 123 days.\nNext line.";
+        assert_eq!(find_matches_with(input, &Gpt4Pretokenizer), crate::find_matches(input));
+    }
+
+    proptest! {
+        #[test]
+        fn proptest_gpt2_matches_reference_regex(s in "\\PC*") {
+            let regex_result = run_regex(GPT2_PATTERN, &s);
+            let library_result = find_matches_with(&s, &Gpt2Pretokenizer);
+
+            assert_eq!(regex_result, library_result, "Mismatch found for input: {:?}", s);
+        }
+    }
+}